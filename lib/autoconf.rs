@@ -4,7 +4,6 @@ use std::io;
 use std::thread;
 use std::time;
 
-use base64;
 use rand::{Rng, OsRng};
 
 use trust_dns::udp::UdpClientConnection;
@@ -14,11 +13,10 @@ use trust_dns::op::Message;
 use trust_dns::rr::{DNSClass, Name, RecordType};
 use trust_dns::rr::resource::Record;
 
-use super::duration_to_micros;
+use super::{duration_to_micros, plausible_label, PLAUSIBLE_DOMAINS};
 
 /// Good known hostname
 const KNOWN_DNS_HIT: &str = "www.google.com";
-const KNOWN_DNS_MISS: &str = "xipoconf.example.com";
 
 #[derive(Debug)]
 pub struct AutoConfig {
@@ -34,6 +32,10 @@ pub struct AutoConfig {
     nxdomain_soa: io::Result<bool>,
     /// True if NXDOMAIN response returns domain SOA record
     nxdomain_soa_cache: io::Result<bool>,
+    /// TTL of a freshly-resolved (never queried before) NXDOMAIN SOA,
+    /// used as the baseline a cached copy's TTL must have counted down
+    /// from.
+    baseline_ttl: io::Result<u32>,
 }
 
 impl AutoConfig {
@@ -43,6 +45,7 @@ impl AutoConfig {
         let ttl_countdown = test_ttl_countdown(server);
         let nxdomain_soa = test_nxdomain_soa(server);
         let nxdomain_soa_cache = test_nxdomain_soa_cache(server);
+        let baseline_ttl = test_baseline_ttl(server);
 
         Ok(Self {
             server,
@@ -51,8 +54,35 @@ impl AutoConfig {
             ttl_countdown,
             nxdomain_soa,
             nxdomain_soa_cache,
+            baseline_ttl,
         })
     }
+
+    /// True if the server answered a UDP query at all. `Xipology` falls
+    /// back to TCP for both priming and timing reads when this is false.
+    pub fn supports_udp(self: &Self) -> bool {
+        self.supports_udp.as_ref().map(|&b| b).unwrap_or(false)
+    }
+
+    /// True if the resolver's negative-answer caching is visible enough
+    /// to use as a hit/miss oracle in its own right: a repeat NXDOMAIN
+    /// lookup of the *same* name comes back with a lower SOA TTL than
+    /// the first one, proving the second answer was served from cache
+    /// rather than freshly resolved. (`ttl_countdown` only shows that
+    /// *positive* answers for an unrelated, already-popular name like
+    /// `www.google.com` count down, which is true of virtually every
+    /// live resolver and says nothing about whether our own zone's
+    /// negative answers get cached.)
+    pub fn caches_negative_answers(self: &Self) -> bool {
+        self.nxdomain_soa_cache.as_ref().map(|&b| b).unwrap_or(false)
+    }
+
+    /// The baseline (uncached) NXDOMAIN SOA TTL observed during
+    /// interrogation, if any. A later query returning a lower TTL for the
+    /// same name indicates the resolver already had it cached.
+    pub fn baseline_ttl(self: &Self) -> Option<u32> {
+        self.baseline_ttl.as_ref().ok().cloned()
+    }
 }
 
 /// Generic DNS query using UDP
@@ -73,13 +103,16 @@ fn query_tcp(server: SocketAddr, name: &Name) -> io::Result<Message> {
     )
 }
 
-/// Generate random dns name to query from `KNOWN_DNS_MISS` domain.
+/// Generate a random, pronounceable dns name under a rotating miss
+/// domain. Guaranteed NXDOMAIN since the label is never registered, but
+/// shaped to look like an ordinary hostname rather than encoded data.
 fn random_name() -> Name {
     let mut rng = OsRng::new().expect("OsRng::new");
-    let mut buf = [0u8; 32];
+    let mut buf = [0u8; 16];
     rng.fill_bytes(&mut buf);
-    let label = base64::encode(&buf);
-    let name = format!("{}.{}", label, KNOWN_DNS_MISS);
+    let label = plausible_label(&buf);
+    let domain = PLAUSIBLE_DOMAINS[rng.gen_range(0, PLAUSIBLE_DOMAINS.len())];
+    let name = format!("{}.{}", label, domain);
     Name::from_str(&name).expect("Name::from_str")
 }
 
@@ -149,24 +182,53 @@ pub fn test_nxdomain_soa(server: SocketAddr) -> io::Result<bool> {
     Ok(!soa.is_empty())
 }
 
+/// TTL of a never-before-queried NXDOMAIN SOA, to use as the "fully
+/// fresh" baseline a cached copy's TTL must be lower than.
+pub fn test_baseline_ttl(server: SocketAddr) -> io::Result<u32> {
+    let name = random_name();
+    let mut result = query_udp(server, &name)?;
+    assert!(result.answers().is_empty());
+
+    let ns = result.take_name_servers();
+    let soa = ns.iter().find(|r| r.rr_type() == RecordType::SOA);
+
+    match soa {
+        Some(record) => Ok(record.ttl()),
+        None => Err(io::Error::new(io::ErrorKind::NotFound, "no SOA in response")),
+    }
+}
+
+/// Test whether a repeat NXDOMAIN query for the same name comes back
+/// with a visibly lower SOA TTL the second time. Per RFC 2308 almost
+/// any resolver returns *some* SOA on NXDOMAIN regardless of caching, so
+/// merely checking both responses carry one (as this used to) is true
+/// of nearly every server and proves nothing; only a TTL that actually
+/// counted down shows the second answer was served from cache.
 pub fn test_nxdomain_soa_cache(server: SocketAddr) -> io::Result<bool> {
-    fn query_soa(server: SocketAddr, name: &Name) -> io::Result<Vec<Record>> {
+    fn query_soa_ttl(server: SocketAddr, name: &Name) -> io::Result<u32> {
         let mut result = query_udp(server, name)?;
         assert!(result.answers().is_empty());
-        let ns = result.take_name_servers();
-        Ok(
-            ns.iter()
-                .filter(|r| r.rr_type() == RecordType::SOA)
-                .cloned()
-                .collect(),
-        )
+        result
+            .take_name_servers()
+            .iter()
+            .find(|r| r.rr_type() == RecordType::SOA)
+            .map(|r| r.ttl())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no SOA in response"))
     }
 
     let name = random_name();
-    let soa1 = query_soa(server, &name)?;
-    let soa2 = query_soa(server, &name)?;
+    let ttl1 = query_soa_ttl(server, &name)?;
+    debug!("First NXDOMAIN SOA TTL: {:?}", ttl1);
+
+    // Sleeping 1.001 seconds, as `test_ttl_countdown` does, so a
+    // genuinely cached SOA's TTL has visibly counted down; an uncached
+    // (freshly re-resolved) negative answer would instead come back at
+    // or above the zone's full SOA TTL.
+    thread::sleep(time::Duration::from_millis(1001));
+    let ttl2 = query_soa_ttl(server, &name)?;
+    debug!("Second NXDOMAIN SOA TTL: {:?}", ttl2);
 
-    Ok(!soa1.is_empty() && !soa2.is_empty())
+    Ok(ttl2 < ttl1)
 }
 
 
@@ -11,8 +11,13 @@ extern crate trust_dns;
 pub mod autoconf;
 pub use autoconf::AutoConfig;
 
+pub mod responder;
+pub use responder::{default_rdata, Responder, ZoneRecord};
+
 mod utils;
 pub use utils::*;
 
 mod xipology;
-pub use xipology::{Xipology, NameDerivator, ReadError};
+pub use xipology::{
+    Xipology, NameDerivator, ReadError, FecMode, NameStyle, SlotStatus, ReadBytesResult,
+};
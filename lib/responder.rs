@@ -0,0 +1,228 @@
+//! A small authoritative responder for the zone `Xipology` derives its
+//! names under.
+//!
+//! The rest of this crate assumes that poking a name makes the target
+//! resolver cache it, but whether that actually happens depends on the
+//! resolver getting a real answer (even an NXDOMAIN with an SOA) from
+//! whoever is authoritative for it — and `next_name`/`random_name` point
+//! at `example.*` domains, which nobody running this tool controls. Running a
+//! `Responder` for the derived zone and pointing the target resolver's
+//! recursion at it turns that into a guarantee: every poked name gets a
+//! real, TTL-controlled answer, so the hit/miss gap `QueryTimes` measures
+//! is as reliable as the TTL we chose to hand out.
+//!
+//! Query parsing itself is left to `trust_dns::op::Message`'s own
+//! decoder, which already bounds compression-pointer following; this
+//! module adds its own belt-and-suspenders bounds: `MAX_QUERY_LEN` in
+//! front of the decoder, so an oversized datagram is rejected before it
+//! is parsed, and `MAX_LABELS` after it, so a decoded question with too
+//! many labels is rejected before it is looked up.
+
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+
+use trust_dns::op::{Message, MessageType, OpCode, ResponseCode};
+use trust_dns::rr::rdata::{MX, SOA, SRV, TXT};
+use trust_dns::rr::{DNSClass, Name, RData, Record, RecordType};
+use trust_dns::serialize::binary::{BinDecoder, BinEncodable, BinEncoder};
+
+/// Maximum encoded query size accepted before even attempting to parse
+/// it, so an oversized UDP datagram never reaches the decoder at all.
+const MAX_QUERY_LEN: usize = 512;
+
+/// Maximum number of labels accepted in a query name, bounding the work
+/// `answer` does per query regardless of how deep a crafted name is.
+const MAX_LABELS: usize = 32;
+
+/// One statically-configured answer this responder will give for an
+/// exact `(name, RecordType)` match.
+#[derive(Clone, Debug)]
+pub struct ZoneRecord {
+    pub name: Name,
+    pub rtype: RecordType,
+    pub rdata: RData,
+    pub ttl: u32,
+}
+
+/// An authoritative responder for a single zone, answering only the
+/// records it has been `insert`ed with and NXDOMAIN (with a real SOA,
+/// carrying `ttl`) for everything else.
+pub struct Responder {
+    zone: Name,
+    soa: Record,
+    records: RwLock<Vec<ZoneRecord>>,
+}
+
+impl Responder {
+    /// Build a responder authoritative for `zone`, handing out its own
+    /// SOA (and thus NXDOMAIN) with `ttl` until records are primed via
+    /// `insert`.
+    pub fn new(zone: Name, ttl: u32) -> Self {
+        let mut soa = Record::new();
+        soa.set_name(zone.clone())
+            .set_rr_type(RecordType::SOA)
+            .set_dns_class(DNSClass::IN)
+            .set_ttl(ttl)
+            .set_rdata(RData::SOA(SOA::new(
+                zone.clone(),
+                Name::from_str(&format!("hostmaster.{}", zone)).expect("Name::from_str"),
+                1,
+                ttl as i32,
+                ttl as i32,
+                (ttl as i32) * 2,
+                ttl,
+            )));
+
+        Self {
+            zone,
+            soa,
+            records: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Prime the zone with an exact-match answer. The writer must insert
+    /// the same `(name, rtype)` pairs it pokes, and the reader must query
+    /// this responder (directly, or via a resolver recursing to it) for
+    /// the priming to be visible to it.
+    pub fn insert(self: &Self, name: Name, rtype: RecordType, rdata: RData, ttl: u32) {
+        let mut records = self.records.write().expect("records.write");
+        records.push(ZoneRecord {
+            name,
+            rtype,
+            rdata,
+            ttl,
+        });
+    }
+
+    fn answer(self: &Self, name: &Name, rtype: RecordType) -> Vec<Record> {
+        if !self.zone.zone_of(name) {
+            return Vec::new();
+        }
+
+        let records = self.records.read().expect("records.read");
+        records
+            .iter()
+            .filter(|r| r.rtype == rtype && &r.name == name)
+            .map(|r| {
+                let mut record = Record::new();
+                record
+                    .set_name(r.name.clone())
+                    .set_rr_type(r.rtype)
+                    .set_dns_class(DNSClass::IN)
+                    .set_ttl(r.ttl)
+                    .set_rdata(r.rdata.clone());
+                record.clone()
+            })
+            .collect()
+    }
+
+    /// Decode one request datagram, build its response, and encode it
+    /// back. A datagram that is too large or does not decode returns an
+    /// error (and so gets no reply); a decoded request that does not
+    /// carry exactly one question within `MAX_LABELS` gets `FormErr`
+    /// rather than being inspected further.
+    fn handle(self: &Self, query: &[u8]) -> io::Result<Vec<u8>> {
+        if query.len() > MAX_QUERY_LEN {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "query too large"));
+        }
+
+        let mut decoder = BinDecoder::new(query);
+        let request = Message::read(&mut decoder)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+
+        let mut response = Message::new();
+        response.set_id(request.id());
+        response.set_message_type(MessageType::Response);
+        response.set_op_code(OpCode::Query);
+        response.set_recursion_desired(request.recursion_desired());
+        response.set_recursion_available(false);
+
+        let question = match request.queries().first() {
+            Some(question)
+                if request.queries().len() == 1 &&
+                       question.name().num_labels() as usize <= MAX_LABELS => question,
+            _ => {
+                response.set_response_code(ResponseCode::FormErr);
+                return encode(&response);
+            }
+        };
+        response.add_query(question.clone());
+
+        let answers = self.answer(question.name(), question.query_type());
+        if answers.is_empty() {
+            response.set_response_code(ResponseCode::NXDomain);
+            response.add_name_server(self.soa.clone());
+        } else {
+            response.set_response_code(ResponseCode::NoError);
+            response.set_authoritative(true);
+            for record in answers {
+                response.add_answer(record);
+            }
+        }
+
+        encode(&response)
+    }
+
+    /// Run the responder on `bind_addr` until the process exits or
+    /// receiving from the socket fails; a failed reply is only logged.
+    /// Blocking and single-threaded: meant to run
+    /// alongside a covert-channel writer priming its own zone, not as a
+    /// general-purpose authoritative server.
+    pub fn serve(self: &Arc<Self>, bind_addr: SocketAddr) -> io::Result<()> {
+        let socket = UdpSocket::bind(bind_addr)?;
+        // One byte of headroom, so a datagram longer than `MAX_QUERY_LEN`
+        // arrives as `MAX_QUERY_LEN + 1` bytes and `handle` rejects it
+        // instead of decoding a truncated prefix.
+        let mut buf = [0u8; MAX_QUERY_LEN + 1];
+
+        loop {
+            let (len, peer) = socket.recv_from(&mut buf)?;
+            match self.handle(&buf[..len]) {
+                Ok(response) => {
+                    if let Err(err) = socket.send_to(&response, peer) {
+                        debug!("Error replying to {}: {}", peer, err);
+                    }
+                }
+                Err(err) => {
+                    debug!("Error handling query from {}: {}", peer, err);
+                }
+            }
+        }
+    }
+}
+
+/// A plausible default `RData` for `rtype`, so a caller priming a
+/// `Responder` with a plan of `(name, rtype)` pairs (see
+/// `Xipology::plan_write_bytes`) does not have to construct one itself
+/// for record types it does not care to make more specific.
+pub fn default_rdata(rtype: RecordType) -> RData {
+    match rtype {
+        RecordType::A => RData::A(Ipv4Addr::new(203, 0, 113, 1)),
+        RecordType::AAAA => RData::AAAA(Ipv6Addr::new(0x2001, 0x0db8, 0, 0, 0, 0, 0, 1)),
+        RecordType::TXT => RData::TXT(TXT::new(vec!["v=xipo1".to_string()])),
+        RecordType::MX => {
+            RData::MX(MX::new(10, Name::from_str("mail.example.com.").expect("Name::from_str")))
+        }
+        _ => {
+            RData::SRV(SRV::new(
+                0,
+                0,
+                0,
+                Name::from_str("target.example.com.").expect("Name::from_str"),
+            ))
+        }
+    }
+}
+
+fn encode(message: &Message) -> io::Result<Vec<u8>> {
+    let mut buf = Vec::with_capacity(512);
+    {
+        let mut encoder = BinEncoder::new(&mut buf);
+        message
+            .emit(&mut encoder)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+    }
+    Ok(buf)
+}
@@ -40,3 +40,39 @@ pub fn duration_to_micros(time: time::Duration) -> f64 {
     let subsecs = time.subsec_nanos() as f64 * 1e-3;
     secs + subsecs
 }
+
+/// Rotating pool of plausible-looking parent domains, shared by
+/// `autoconf`'s NXDOMAIN probes and `Xipology`'s default
+/// `NameStyle::Camouflage` base domains, so neither can drift from the
+/// other when the list is edited.
+pub const PLAUSIBLE_DOMAINS: [&str; 4] = [
+    "cdn.example.net.",
+    "static.example.org.",
+    "assets.example.com.",
+    "img.example.io.",
+];
+
+const CONSONANTS: &[u8] = b"bcdfghjklmnpqrstvwxyz";
+const VOWELS: &[u8] = b"aeiou";
+
+/// Turn arbitrary bytes into a human-plausible, pronounceable DNS label
+/// (consonant-vowel syllables) instead of raw base64, so a query does
+/// not stick out as obviously encoded data.
+///
+/// ```rust
+/// use xipolib::plausible_label;
+///
+/// let label = plausible_label(&[0, 0, 0, 0]);
+/// assert_eq!(label, "baba");
+/// ```
+pub fn plausible_label(bytes: &[u8]) -> String {
+    let mut label = String::with_capacity(bytes.len());
+    for pair in bytes.chunks(2) {
+        let c = CONSONANTS[pair[0] as usize % CONSONANTS.len()];
+        label.push(c as char);
+        if let Some(&v) = pair.get(1) {
+            label.push(VOWELS[v as usize % VOWELS.len()] as char);
+        }
+    }
+    label
+}
@@ -9,35 +9,245 @@ use base64;
 
 use rand::{Rng, OsRng};
 use rayon::prelude::*;
+use ring::aead;
 use ring::digest;
 use ring::hkdf;
 use ring::hmac::SigningKey;
 
 use trust_dns::client::{Client, SyncClient};
+use trust_dns::op::Message;
 use trust_dns::rr::{DNSClass, RecordType, Name};
+use trust_dns::tcp::TcpClientConnection;
 use trust_dns::udp::UdpClientConnection;
 
-use super::{duration_to_micros, get_bit, set_bit};
+use super::autoconf::AutoConfig;
+use super::{duration_to_micros, get_bit, plausible_label, set_bit, PLAUSIBLE_DOMAINS};
 
 /// How many decoy bits per one byte of output
 const DECOY_BITS: usize = 0;
 
-type Xipo = (XipoBits, Name);
+/// Record types a camouflaged query is allowed to pick among, so a
+/// resolver sees a realistic mix of lookups rather than nothing but SRV.
+const CAMOUFLAGE_RECORD_TYPES: [RecordType; 5] = [
+    RecordType::A,
+    RecordType::AAAA,
+    RecordType::TXT,
+    RecordType::MX,
+    RecordType::SRV,
+];
+
+/// Default rotating pool of plausible parent domains for camouflaged
+/// names. Configurable via `Xipology::set_base_domains`.
+fn default_base_domains() -> Vec<String> {
+    PLAUSIBLE_DOMAINS.iter().map(|s| s.to_string()).collect()
+}
+
+/// Length in bytes of the Poly1305 authentication tag.
+const TAG_LEN: usize = 16;
+
+/// `hkdf::expand` labels used to derive the payload AEAD key material.
+/// These are distinct from the (empty) info string used by
+/// `NameDerivator::next_name`, so deriving them never perturbs the
+/// name-derivation stream.
+const AEAD_KEY_LABEL: &[u8] = b"xipology aead key v1";
+const AEAD_NONCE_LABEL: &[u8] = b"xipology aead nonce v1";
+
+/// Width, in bytes, of the per-message nonce counter carried in the
+/// clear ahead of the tag (the same way a nonce is ordinarily sent
+/// alongside an AEAD ciphertext).
+const NONCE_COUNTER_LEN: usize = 8;
+
+type Xipo = (XipoBits, Name, RecordType);
+
+/// Which protocol `poke_name` speaks, decided once from `AutoConfig`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Transport {
+    Udp,
+    Tcp,
+}
+
+/// How a query is classified as a cache hit or miss, decided once from
+/// `AutoConfig`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum HitOracle {
+    /// The original scheme: classify by comparing a query's latency
+    /// against the `QueryTimes` measured for known hits/misses.
+    Timing,
+    /// For resolvers whose negative-answer cache is visible in the
+    /// response itself: a returned SOA TTL lower than the uncached
+    /// baseline means the name was already cached (a hit).
+    SoaCache,
+}
+
+/// How names are shaped on the wire.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NameStyle {
+    /// The original scheme: raw base64 labels under
+    /// `xipology.example.com`, always queried as `SRV`. Cheap, but a
+    /// fixed fingerprint an IDS or NAT redirect can match trivially.
+    Raw,
+    /// Pronounceable labels under a rotating base domain, queried with a
+    /// record type chosen per-name — both deterministically derived
+    /// from the shared secret, so reader and writer agree without
+    /// exchanging anything extra.
+    Camouflage,
+}
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum XipoBits {
     Data(u8),
+    /// One of the 14 Hamming(7,4) codeword positions (two 7-bit blocks
+    /// per byte), used instead of `Data`/`Parity` under `FecMode::Hamming74`.
+    Fec(u8),
+    /// One bit of a framed slot's sequence number (0..8).
+    Seq(u8),
+    /// One bit of a framed slot's 3-bit checksum nibble.
+    Checksum(u8),
     Decoy,
     Guard,
     Parity,
     Reservation,
 }
 
+/// Forward error correction scheme used to encode each byte's bits into
+/// names.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FecMode {
+    /// A lone even-parity bit per byte (9 names/byte). Can *detect* a
+    /// single flipped bit but not correct it. Kept around for
+    /// bandwidth-constrained use.
+    Parity,
+    /// Hamming(7,4): each nibble becomes a 7-bit block that corrects any
+    /// single-bit error (14 names/byte).
+    Hamming74,
+}
+
+/// Encode a 4-bit nibble into a Hamming(7,4) codeword
+/// `[p1, p2, d1, p3, d2, d3, d4]`, where `p1` covers bit positions
+/// `{1,3,5,7}`, `p2` covers `{2,3,6,7}` and `p3` covers `{4,5,6,7}`
+/// (1-based).
+fn hamming_encode(nibble: u8) -> [bool; 7] {
+    let d1 = get_bit(nibble, 0) != 0;
+    let d2 = get_bit(nibble, 1) != 0;
+    let d3 = get_bit(nibble, 2) != 0;
+    let d4 = get_bit(nibble, 3) != 0;
+
+    let p1 = d1 ^ d2 ^ d4;
+    let p2 = d1 ^ d3 ^ d4;
+    let p3 = d2 ^ d3 ^ d4;
+
+    [p1, p2, d1, p3, d2, d3, d4]
+}
+
+/// Correct a single-bit error in a Hamming(7,4) codeword (if any) and
+/// extract the original nibble.
+fn hamming_decode(codeword: &mut [bool; 7]) -> u8 {
+    let s1 = codeword[0] ^ codeword[2] ^ codeword[4] ^ codeword[6];
+    let s2 = codeword[1] ^ codeword[2] ^ codeword[5] ^ codeword[6];
+    let s3 = codeword[3] ^ codeword[4] ^ codeword[5] ^ codeword[6];
+    let syndrome = s1 as u8 + (s2 as u8) * 2 + (s3 as u8) * 4;
+
+    if syndrome != 0 {
+        let bad = (syndrome - 1) as usize;
+        codeword[bad] = !codeword[bad];
+    }
+
+    let mut nibble = 0u8;
+    if codeword[2] {
+        set_bit(&mut nibble, 0);
+    }
+    if codeword[4] {
+        set_bit(&mut nibble, 1);
+    }
+    if codeword[5] {
+        set_bit(&mut nibble, 2);
+    }
+    if codeword[6] {
+        set_bit(&mut nibble, 3);
+    }
+    nibble
+}
+
+/// 3-bit per-slot checksum, folded from a byte's own bits. Lets a reader
+/// notice a slot whose payload bits were individually readable but don't
+/// belong to the sequence position it was read at.
+fn frame_checksum(byte: u8) -> u8 {
+    ((byte >> 5) ^ (byte >> 2) ^ byte) & 0b111
+}
+
+/// CRC-8/SMBUS (poly 0x07, init 0x00) over the whole framed message,
+/// carried as a trailing slot so a reader can tell a fully-recovered
+/// message apart from one that merely looks complete.
+fn crc8(data: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    for &byte in data {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// Outcome of reading one sequence-numbered slot of a framed message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlotStatus {
+    /// The slot's Reservation/Guard/FEC/checksum all checked out.
+    Recovered(u8),
+    /// The slot's Reservation marker was never observed (cache miss) or
+    /// its header/FEC did not check out.
+    Missing,
+    /// Somebody else (or a previous pass) has already consumed this slot.
+    Consumed,
+}
+
+/// Structured outcome of `Xipology::read_bytes`, describing recovery of
+/// every sequence-numbered slot instead of silently papering over gaps
+/// with `b' '`.
+#[derive(Debug)]
+pub struct ReadBytesResult {
+    /// One entry per slot in sequence order. The final entry carries the
+    /// whole-message CRC-8 rather than a byte of the framed message.
+    pub slots: Vec<SlotStatus>,
+    /// `Some` only once every slot recovered, the trailing CRC matched,
+    /// and the AEAD tag verified.
+    pub message: Option<Vec<u8>>,
+    /// Set when every slot and the CRC checked out but the AEAD tag did
+    /// not verify: the stored bytes were corrupted or forged rather than
+    /// merely incomplete.
+    pub tamper_detected: bool,
+    /// The exact `(bit, name, rtype)` triples queried to produce each
+    /// entry in `slots`, in the same order. `Xipology::retry_missing`
+    /// re-queries these rather than calling `next_xipo` again, since the
+    /// forward-secret chain has already moved past this message and
+    /// would derive different names for the same sequence position.
+    inputs: Vec<Vec<Xipo>>,
+}
+
+impl ReadBytesResult {
+    /// Sequence positions a caller should re-poll, in order.
+    pub fn missing_seqs(&self) -> Vec<u8> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(seq, status)| match *status {
+                SlotStatus::Recovered(_) => None,
+                _ => Some(seq as u8),
+            })
+            .collect()
+    }
+}
+
 #[derive(Debug)]
 pub enum ReadError {
     Free,
     Consumed,
     Parity,
+    Decrypt,
     IO(io::Error),
 }
 
@@ -47,10 +257,30 @@ pub struct Xipology {
     secret: Vec<u8>,
     server: SocketAddr,
     query_times: Option<super::autoconf::QueryTimes>,
+    aead_key: [u8; 32],
+    /// PRK a fresh nonce is `expand`ed from for every message, keyed on
+    /// `nonce_counter`, so no two messages are ever sealed under the
+    /// same (key, nonce) pair.
+    aead_nonce_prk: SigningKey,
+    /// Monotonic across this instance's whole lifetime: never reset back
+    /// to 0 by `reset`/`change_secret`, so a repeated call can never
+    /// reissue a counter value (and therefore a nonce) `encrypt` has
+    /// already used, even against a reused secret.
+    nonce_counter: u64,
+    fec: FecMode,
+    name_style: NameStyle,
+    base_domains: Vec<String>,
+    transport: Transport,
+    hit_oracle: HitOracle,
+    baseline_ttl: Option<u32>,
 }
 
 impl Xipology {
-    pub fn from_secret(server: SocketAddr, secret: Vec<u8>) -> Self {
+    /// Interrogates `server` with `AutoConfig` and builds a channel
+    /// adapted to what it finds: TCP instead of UDP when UDP is filtered,
+    /// and a SOA-TTL hit/miss oracle instead of timing thresholds when
+    /// the resolver's negative-answer caching is visible in the response.
+    pub fn from_secret(server: SocketAddr, secret: Vec<u8>) -> io::Result<Self> {
         let mut derivator = NameDerivator::from_secret(&secret);
         let query_times = None;
 
@@ -60,13 +290,68 @@ impl Xipology {
             NameDerivator::from_secret(&decoy)
         };
 
-        Self {
+        let (aead_key, aead_nonce_prk) = derive_aead_material(&secret);
+
+        let autoconfig = AutoConfig::interrogate(server)?;
+        let transport = if autoconfig.supports_udp() {
+            Transport::Udp
+        } else {
+            Transport::Tcp
+        };
+        let hit_oracle = if autoconfig.caches_negative_answers() {
+            HitOracle::SoaCache
+        } else {
+            HitOracle::Timing
+        };
+        // Not measured eagerly: `AutoConfig::baseline_ttl` was probed
+        // against autoconf's own miss-domain rotation, which is an
+        // unrelated zone with its own (uncontrolled) SOA TTL. The real
+        // baseline depends on which zone/style we actually query, so
+        // `measure_baseline_ttl` derives it lazily against our own
+        // `probe_name` instead.
+        let baseline_ttl = None;
+
+        Ok(Self {
             derivator,
             decoy,
             secret,
             server,
             query_times,
-        }
+            aead_key,
+            aead_nonce_prk,
+            nonce_counter: 0,
+            fec: FecMode::Parity,
+            name_style: NameStyle::Raw,
+            base_domains: default_base_domains(),
+            transport,
+            hit_oracle,
+            baseline_ttl,
+        })
+    }
+
+    /// Select the forward error correction scheme used for subsequent
+    /// writes and reads. Both ends of the channel must agree.
+    pub fn set_fec_mode(self: &mut Self, fec: FecMode) {
+        self.fec = fec;
+    }
+
+    /// Select how names (and their record types) are shaped on the
+    /// wire. Both ends of the channel must agree. Invalidates any
+    /// measured `baseline_ttl`, since it is only valid for the zone the
+    /// previous style queried.
+    pub fn set_name_style(self: &mut Self, name_style: NameStyle) {
+        self.name_style = name_style;
+        self.baseline_ttl = None;
+    }
+
+    /// Replace the rotating pool of base domains used under
+    /// `NameStyle::Camouflage`. Both ends of the channel must agree.
+    /// Invalidates any measured `baseline_ttl`, since it is only valid
+    /// for the previous pool of domains.
+    pub fn set_base_domains(self: &mut Self, base_domains: Vec<String>) {
+        assert!(!base_domains.is_empty());
+        self.base_domains = base_domains;
+        self.baseline_ttl = None;
     }
 
     pub fn change_secret(self: &mut Self, secret: Vec<u8>) {
@@ -74,6 +359,16 @@ impl Xipology {
         self.reset();
     }
 
+    /// Deliberately does *not* reset `nonce_counter`: `aead_key` and
+    /// `aead_nonce_prk` are re-derived straight from `secret`, so a
+    /// second `reset`/`change_secret` call with the same secret bytes
+    /// would reproduce both exactly. Resetting the counter to 0 as well
+    /// would then reseal the next message under a (key, nonce) pair
+    /// already used before this call — the exact ChaCha20-Poly1305 reuse
+    /// `encrypt`/`derive_nonce` exist to rule out. Leaving the counter
+    /// monotonic across the instance's whole lifetime, regardless of how
+    /// many times this runs, keeps every nonce this instance issues
+    /// unique.
     pub fn reset(self: &mut Self) {
         self.derivator = NameDerivator::from_secret(&self.secret);
         self.query_times = None;
@@ -82,42 +377,157 @@ impl Xipology {
             self.derivator.hkdf_extract_and_expand(&mut decoy);
             NameDerivator::from_secret(&decoy)
         };
+        let (aead_key, aead_nonce_prk) = derive_aead_material(&self.secret);
+        self.aead_key = aead_key;
+        self.aead_nonce_prk = aead_nonce_prk;
+    }
+
+    /// Seal `plaintext` with ChaCha20-Poly1305 under a nonce derived
+    /// fresh for this call (see `derive_nonce`), returning the 8-byte
+    /// counter that produced it, followed by the 16-byte tag, followed
+    /// by the ciphertext. The caller splits this framed message across
+    /// the per-byte `byte_output` bit pattern. Carrying the counter in
+    /// the clear is what lets `decrypt` reconstruct the same nonce
+    /// without the two ends otherwise having to stay in lockstep on how
+    /// many messages have been sealed.
+    fn encrypt(self: &mut Self, plaintext: &[u8]) -> Vec<u8> {
+        let counter = self.nonce_counter;
+        self.nonce_counter += 1;
+        let nonce = derive_nonce(&self.aead_nonce_prk, counter);
+
+        let key = aead::SealingKey::new(&aead::CHACHA20_POLY1305, &self.aead_key)
+            .expect("SealingKey::new");
+
+        let mut in_out = plaintext.to_vec();
+        in_out.extend_from_slice(&[0u8; TAG_LEN]);
+
+        let out_len = aead::seal_in_place(&key, &nonce, &[], &mut in_out, TAG_LEN)
+            .expect("seal_in_place");
+        let tag = in_out.split_off(out_len - TAG_LEN);
+
+        let mut framed = counter.to_be_bytes().to_vec();
+        framed.extend_from_slice(&tag);
+        framed.extend_from_slice(&in_out[..out_len - TAG_LEN]);
+        framed
+    }
+
+    /// Verify and open a framed message produced by `encrypt`. Returns
+    /// `ReadError::Decrypt` rather than plaintext if the tag does not
+    /// match, so a forged or corrupted cache entry is never handed back
+    /// to the caller as if it were genuine.
+    fn decrypt(self: &Self, framed: &[u8]) -> Result<Vec<u8>, ReadError> {
+        if framed.len() < NONCE_COUNTER_LEN + TAG_LEN {
+            return Err(ReadError::Decrypt);
+        }
+        let (counter_bytes, rest) = framed.split_at(NONCE_COUNTER_LEN);
+        let mut counter_buf = [0u8; NONCE_COUNTER_LEN];
+        counter_buf.copy_from_slice(counter_bytes);
+        let nonce = derive_nonce(&self.aead_nonce_prk, u64::from_be_bytes(counter_buf));
+
+        let (tag, ciphertext) = rest.split_at(TAG_LEN);
+
+        let key = aead::OpeningKey::new(&aead::CHACHA20_POLY1305, &self.aead_key)
+            .expect("OpeningKey::new");
+
+        let mut in_out = ciphertext.to_vec();
+        in_out.extend_from_slice(tag);
+
+        let plaintext = aead::open_in_place(&key, &nonce, &[], 0, &mut in_out)
+            .map_err(|_| ReadError::Decrypt)?;
+        Ok(plaintext.to_vec())
+    }
+
+    /// Produce the next name (and the record type it should be queried
+    /// as) from the name-derivation chain, per the configured
+    /// `NameStyle`.
+    fn next_xipo(self: &mut Self) -> (Name, RecordType) {
+        match self.name_style {
+            NameStyle::Raw => (self.derivator.next_name(), RecordType::SRV),
+            NameStyle::Camouflage => self.next_camouflage_name(),
+        }
+    }
+
+    /// Derive a pronounceable label under a rotating base domain and a
+    /// record type, all from the same forward-secret chain step so a
+    /// reader following the identical sequence of calls agrees on both.
+    fn next_camouflage_name(self: &mut Self) -> (Name, RecordType) {
+        let mut selector = [0u8; 16];
+        self.derivator.hkdf_extract_and_expand(&mut selector);
+
+        let rtype = CAMOUFLAGE_RECORD_TYPES[selector[0] as usize % CAMOUFLAGE_RECORD_TYPES.len()];
+        let domain = &self.base_domains[selector[1] as usize % self.base_domains.len()];
+
+        let label1 = plausible_label(&selector[2..9]);
+        let label2 = plausible_label(&selector[9..16]);
+
+        let name = format!("{}.{}.{}", label1, label2, domain);
+        (Name::from_str(&name).expect("Name::from_str"), rtype)
     }
 
     fn byte_output(self: &mut Self, byte: u8) -> Vec<Xipo> {
         info!("write_byte({:?})", byte);
 
         let mut output = Vec::new();
-        let mut parity = false;
 
         // Reservation
-        output.push((XipoBits::Reservation, self.derivator.next_name()));
+        let (name, rtype) = self.next_xipo();
+        output.push((XipoBits::Reservation, name, rtype));
 
         // Guard (do not touch it on write)
-        let _ = self.derivator.next_name();
+        let _ = self.next_xipo();
 
         // Payload
+        match self.fec {
+            FecMode::Parity => self.byte_output_parity(byte, &mut output),
+            FecMode::Hamming74 => self.byte_output_hamming(byte, &mut output),
+        }
+
+        self.append_decoys(&mut output);
+
+        output
+    }
+
+    fn byte_output_parity(self: &mut Self, byte: u8, output: &mut Vec<Xipo>) {
+        let mut parity = false;
+
         for bit in 0..8 {
-            let name = self.derivator.next_name();
+            let (name, rtype) = self.next_xipo();
             if get_bit(byte, bit) > 0 {
-                output.push((XipoBits::Data(bit), name));
+                output.push((XipoBits::Data(bit), name, rtype));
                 parity = parity.not();
             }
         }
 
         // Parity (even)
-        let name = self.derivator.next_name();
+        let (name, rtype) = self.next_xipo();
         if parity {
-            output.push((XipoBits::Parity, name));
+            output.push((XipoBits::Parity, name, rtype));
         }
+    }
 
+    fn byte_output_hamming(self: &mut Self, byte: u8, output: &mut Vec<Xipo>) {
+        let nibbles = [byte & 0x0F, (byte >> 4) & 0x0F];
+
+        for (n, nibble) in nibbles.iter().enumerate() {
+            let codeword = hamming_encode(*nibble);
+            for (i, bit) in codeword.iter().enumerate() {
+                let slot = (n * 7 + i) as u8;
+                let (name, rtype) = self.next_xipo();
+                if *bit {
+                    output.push((XipoBits::Fec(slot), name, rtype));
+                }
+            }
+        }
+    }
+
+    fn append_decoys(self: &mut Self, output: &mut Vec<Xipo>) {
         let mut rng = OsRng::new().expect("OsRng::new");
         if DECOY_BITS > 0 {
             let decoy_flips = rng.gen_range(0, DECOY_BITS);
             let decoy_flops = DECOY_BITS - decoy_flips;
             // Flip up some decoy bits.
             (0..decoy_flips).into_iter().for_each(|_| {
-                let _ = output.push((XipoBits::Decoy, self.decoy.next_name()));
+                let _ = output.push((XipoBits::Decoy, self.decoy.next_name(), RecordType::SRV));
             });
             // Next we advance decoy name generator to consume total of DECOY_BITS
             // of names.
@@ -125,13 +535,133 @@ impl Xipology {
                 let _ = self.decoy.next_name();
             });
         }
+    }
 
+    /// Emit a framed slot's header: an 8-bit sequence number followed by
+    /// a 3-bit checksum of the payload byte, using the same
+    /// presence-means-1 encoding as the data/FEC bits.
+    fn header_output(self: &mut Self, seq: u8, checksum: u8, output: &mut Vec<Xipo>) {
+        for bit in 0..8 {
+            let (name, rtype) = self.next_xipo();
+            if get_bit(seq, bit) > 0 {
+                output.push((XipoBits::Seq(bit), name, rtype));
+            }
+        }
+        for bit in 0..3 {
+            let (name, rtype) = self.next_xipo();
+            if get_bit(checksum, bit) > 0 {
+                output.push((XipoBits::Checksum(bit), name, rtype));
+            }
+        }
+    }
+
+    fn header_input(self: &mut Self, input: &mut Vec<Xipo>) {
+        for bit in 0..8 {
+            let (name, rtype) = self.next_xipo();
+            input.push((XipoBits::Seq(bit), name, rtype));
+        }
+        for bit in 0..3 {
+            let (name, rtype) = self.next_xipo();
+            input.push((XipoBits::Checksum(bit), name, rtype));
+        }
+    }
+
+    /// Like `byte_output`, but prefixed with a sequence-numbered,
+    /// checksummed header so a reader can tell which slot it recovered
+    /// (or tell that it did not).
+    fn framed_byte_output(self: &mut Self, byte: u8, seq: u8) -> Vec<Xipo> {
+        let mut output = Vec::new();
+
+        let (name, rtype) = self.next_xipo();
+        output.push((XipoBits::Reservation, name, rtype));
+        let _ = self.next_xipo(); // Guard: do not touch it on write
+
+        self.header_output(seq, frame_checksum(byte), &mut output);
+
+        match self.fec {
+            FecMode::Parity => self.byte_output_parity(byte, &mut output),
+            FecMode::Hamming74 => self.byte_output_hamming(byte, &mut output),
+        }
+
+        self.append_decoys(&mut output);
         output
     }
 
+    fn framed_byte_input(self: &mut Self) -> Vec<Xipo> {
+        let mut input = Vec::new();
+
+        let (name, rtype) = self.next_xipo();
+        input.push((XipoBits::Reservation, name, rtype));
+        let (name, rtype) = self.next_xipo();
+        input.push((XipoBits::Guard, name, rtype));
+
+        self.header_input(&mut input);
+
+        match self.fec {
+            FecMode::Parity => {
+                for bit in 0..8 {
+                    let (name, rtype) = self.next_xipo();
+                    input.push((XipoBits::Data(bit), name, rtype));
+                }
+                let (name, rtype) = self.next_xipo();
+                input.push((XipoBits::Parity, name, rtype));
+            }
+            FecMode::Hamming74 => {
+                for slot in 0..14u8 {
+                    let (name, rtype) = self.next_xipo();
+                    input.push((XipoBits::Fec(slot), name, rtype));
+                }
+            }
+        }
+
+        self.append_decoys(&mut input);
+        input
+    }
+
+    /// Query and decode one framed slot, checking its Reservation/Guard
+    /// markers, its sequence number and its checksum before trusting the
+    /// recovered byte.
+    fn read_framed_bits(self: &Self, input: &[Xipo], expected_seq: u8) -> SlotStatus {
+        let bits: Vec<XipoBits> = input
+            .par_iter()
+            .map(|&(ref bit, ref name, rtype)| {
+                if self.is_hit(name, rtype) { *bit } else { XipoBits::Decoy }
+            })
+            .collect();
+
+        if !bits.contains(&XipoBits::Reservation) {
+            return SlotStatus::Missing;
+        }
+        if bits.contains(&XipoBits::Guard) {
+            return SlotStatus::Consumed;
+        }
+
+        let mut seq = 0u8;
+        let mut checksum = 0u8;
+        for b in &bits {
+            match *b {
+                XipoBits::Seq(n) => set_bit(&mut seq, n),
+                XipoBits::Checksum(n) => set_bit(&mut checksum, n),
+                _ => {}
+            }
+        }
+
+        let byte = match self.fec {
+            FecMode::Parity => Self::decode_parity(&bits),
+            FecMode::Hamming74 => Ok(Self::decode_hamming(&bits)),
+        };
+
+        match byte {
+            Ok(byte) if seq == expected_seq && frame_checksum(byte) == checksum => {
+                SlotStatus::Recovered(byte)
+            }
+            _ => SlotStatus::Missing,
+        }
+    }
+
     fn write_bits(self: &Self, output: &[Xipo]) -> io::Result<usize> {
-        output.par_iter().for_each(|&(ref bit, ref name)| {
-            match self.poke_name(name) {
+        output.par_iter().for_each(|&(ref bit, ref name, rtype)| {
+            match self.poke_name(name, rtype) {
                 Ok(_) => {
                     debug!("Wrote bit {:?} into name {}", bit, name);
                 }
@@ -150,20 +680,49 @@ impl Xipology {
     }
 
     pub fn write_bytes(self: &mut Self, buf: &[u8]) -> io::Result<usize> {
-        let len = buf.len();
+        let plan = self.plan_write_bytes(buf);
+        self.write_planned(&plan)
+    }
+
+    /// Build the full list of `(name, rtype)` pairs `write_bytes` would
+    /// poke for `buf`, without poking them. Lets a caller prime a
+    /// `Responder` (so the names it's about to write resolve to a real,
+    /// TTL-controlled answer instead of whatever an uncontrolled upstream
+    /// zone happens to do) before anything hits the wire.
+    pub fn plan_write_bytes(self: &mut Self, buf: &[u8]) -> Vec<(Name, RecordType)> {
+        let framed = self.encrypt(buf);
+        let len = framed.len();
         assert!(len > 0 && len < 255);
         let mut output = Vec::new();
 
-        // Length
+        // Length (unframed; read back first via read_byte/byte_input)
         let mut len_byte = self.byte_output(len as u8);
         output.append(&mut len_byte);
 
-        // Payload
-        for byte in buf {
-            let mut b = self.byte_output(*byte);
+        // Payload: nonce counter || tag || ciphertext, one
+        // sequence-numbered, checksummed slot per byte.
+        for (seq, byte) in framed.iter().enumerate() {
+            let mut b = self.framed_byte_output(*byte, seq as u8);
             output.append(&mut b);
         }
 
+        // Trailing whole-message CRC-8, framed as slot `len`.
+        let crc = crc8(&framed);
+        let mut crc_slot = self.framed_byte_output(crc, len as u8);
+        output.append(&mut crc_slot);
+
+        output
+            .into_iter()
+            .map(|(_, name, rtype)| (name, rtype))
+            .collect()
+    }
+
+    /// Poke every `(name, rtype)` pair in `plan`, e.g. one produced by
+    /// `plan_write_bytes`.
+    pub fn write_planned(self: &Self, plan: &[(Name, RecordType)]) -> io::Result<usize> {
+        let output: Vec<Xipo> = plan.iter()
+            .map(|&(ref name, rtype)| (XipoBits::Decoy, name.clone(), rtype))
+            .collect();
         self.write_bits(&output)
     }
 
@@ -171,49 +730,38 @@ impl Xipology {
     fn byte_input(self: &mut Self) -> Vec<Xipo> {
         let mut input = Vec::new();
 
-        input.push((XipoBits::Reservation, self.derivator.next_name()));
-        input.push((XipoBits::Guard, self.derivator.next_name()));
+        let (name, rtype) = self.next_xipo();
+        input.push((XipoBits::Reservation, name, rtype));
+        let (name, rtype) = self.next_xipo();
+        input.push((XipoBits::Guard, name, rtype));
 
-        for bit in 0..8 {
-            input.push((XipoBits::Data(bit), self.derivator.next_name()));
+        match self.fec {
+            FecMode::Parity => {
+                for bit in 0..8 {
+                    let (name, rtype) = self.next_xipo();
+                    input.push((XipoBits::Data(bit), name, rtype));
+                }
+                let (name, rtype) = self.next_xipo();
+                input.push((XipoBits::Parity, name, rtype));
+            }
+            FecMode::Hamming74 => {
+                for slot in 0..14u8 {
+                    let (name, rtype) = self.next_xipo();
+                    input.push((XipoBits::Fec(slot), name, rtype));
+                }
+            }
         }
 
-        input.push((XipoBits::Parity, self.derivator.next_name()));
-
-        let mut rng = OsRng::new().expect("OsRng::new");
-        if DECOY_BITS > 0 {
-            let decoy_flips = rng.gen_range(0, DECOY_BITS);
-            let decoy_flops = DECOY_BITS - decoy_flips;
-            (0..decoy_flips).into_iter().for_each(|_| {
-                let _ = input.push((XipoBits::Decoy, self.decoy.next_name()));
-            });
-            (0..decoy_flops).into_iter().for_each(|_| {
-                let _ = self.decoy.next_name();
-            });
-        }
+        self.append_decoys(&mut input);
 
         input
     }
 
     fn read_bits(self: &Self, input: &[Xipo]) -> Result<u8, ReadError> {
-        let query_times = self.query_times.expect("query_times");
-        let is_hit = |delay: f64| {
-            let md = f64::abs(query_times.miss - delay);
-            let hd = f64::abs(query_times.hit - delay);
-            hd < md
-        };
-
         let bits: Vec<XipoBits> = input
             .par_iter()
-            .map(|&(ref bit, ref name)| {
-                let delay = match self.poke_name(name) {
-                    Ok(d) => d,
-                    Err(err) => {
-                        debug!("Error read bit {:?} from name {}: {}", bit, name, err);
-                        f64::NAN
-                    }
-                };
-                if is_hit(delay) {
+            .map(|&(ref bit, ref name, rtype)| {
+                if self.is_hit(name, rtype) {
                     *bit
                 } else {
                     // 0 bits are read as "Decoy" and ignored
@@ -230,9 +778,19 @@ impl Xipology {
             return Err(ReadError::Consumed);
         }
 
+        let byte = match self.fec {
+            FecMode::Parity => Self::decode_parity(&bits)?,
+            FecMode::Hamming74 => Self::decode_hamming(&bits),
+        };
+
+        info!("Read byte {}", byte);
+        Ok(byte)
+    }
+
+    fn decode_parity(bits: &[XipoBits]) -> Result<u8, ReadError> {
         let mut parity = false;
         let mut byte = 0u8;
-        for b in &bits {
+        for b in bits {
             if let XipoBits::Data(n) = *b {
                 set_bit(&mut byte, n);
                 parity = parity.not();
@@ -245,83 +803,406 @@ impl Xipology {
             return Err(ReadError::Parity);
         }
 
-        info!("Read byte {}", byte);
         Ok(byte)
     }
 
+    fn decode_hamming(bits: &[XipoBits]) -> u8 {
+        let mut slots = [false; 14];
+        for b in bits {
+            if let XipoBits::Fec(slot) = *b {
+                slots[slot as usize] = true;
+            }
+        }
+
+        let mut byte = 0u8;
+        for (n, chunk) in slots.chunks(7).enumerate() {
+            let mut codeword = [
+                chunk[0],
+                chunk[1],
+                chunk[2],
+                chunk[3],
+                chunk[4],
+                chunk[5],
+                chunk[6],
+            ];
+            byte |= hamming_decode(&mut codeword) << (n * 4);
+        }
+
+        byte
+    }
+
+    /// How many epochs ahead of our own a reader will try before giving
+    /// up, to tolerate clock drift against the writer.
+    const RESYNC_EPOCHS: u64 = 4;
+
+    /// Current ratchet epoch. Both ends advance this independently from
+    /// a shared clock; see `advance_epoch`/`advance_epoch_to`.
+    pub fn epoch(self: &Self) -> u64 {
+        self.derivator.epoch()
+    }
+
+    /// Ratchet the name-derivation state forward one epoch, deriving a
+    /// fresh chain key and rolling the root key. Forward-secure: the
+    /// previous epoch's names cannot be recovered from the new state.
+    pub fn advance_epoch(self: &mut Self) {
+        self.derivator.advance_epoch();
+    }
+
+    /// Repeatedly `advance_epoch` until caught up to `target`. Epochs
+    /// only move forward, so `target` must not be behind the current
+    /// epoch.
+    pub fn advance_epoch_to(self: &mut Self, target: u64) {
+        while self.epoch() < target {
+            self.advance_epoch();
+        }
+    }
+
     pub fn read_byte(self: &mut Self) -> Result<u8, ReadError> {
-        if self.query_times.is_none() {
+        if self.hit_oracle == HitOracle::Timing && self.query_times.is_none() {
             debug!("Measuring query times");
             let query_times = super::autoconf::test_query_time_differences(self.server)
                 .map_err(ReadError::IO)?;
             info!("{:?}", query_times);
             self.query_times = Some(query_times);
         }
+        if self.hit_oracle == HitOracle::SoaCache && self.baseline_ttl.is_none() {
+            debug!("Measuring baseline SOA TTL");
+            let baseline_ttl = self.measure_baseline_ttl().map_err(ReadError::IO)?;
+            info!("baseline_ttl = {}", baseline_ttl);
+            self.baseline_ttl = Some(baseline_ttl);
+        }
+
+        // A reader whose clock has drifted a few epochs behind the
+        // writer still has a chance: try this epoch, then a bounded
+        // number of epochs ahead.
+        for _ in 0..Self::RESYNC_EPOCHS {
+            let input = self.byte_input();
+            match self.read_bits(&input) {
+                Ok(byte) => return Ok(byte),
+                Err(ReadError::Free) => {
+                    debug!(
+                        "Epoch {} looked free, trying epoch {}",
+                        self.epoch(),
+                        self.epoch() + 1
+                    );
+                    self.advance_epoch();
+                }
+                Err(err) => return Err(err),
+            }
+        }
 
         let input = self.byte_input();
         self.read_bits(&input)
     }
 
-    pub fn read_bytes(self: &mut Self) -> Result<Vec<u8>, ReadError> {
-        if self.query_times.is_none() {
+    pub fn read_bytes(self: &mut Self) -> Result<ReadBytesResult, ReadError> {
+        if self.hit_oracle == HitOracle::Timing && self.query_times.is_none() {
             debug!("Measuring query times");
             let query_times = super::autoconf::test_query_time_differences(self.server)
                 .map_err(ReadError::IO)?;
             info!("{:?}", query_times);
             self.query_times = Some(query_times);
         }
+        if self.hit_oracle == HitOracle::SoaCache && self.baseline_ttl.is_none() {
+            debug!("Measuring baseline SOA TTL");
+            let baseline_ttl = self.measure_baseline_ttl().map_err(ReadError::IO)?;
+            info!("baseline_ttl = {}", baseline_ttl);
+            self.baseline_ttl = Some(baseline_ttl);
+        }
 
         let len = self.read_byte()?;
         debug!("read_bytes len = {}", len);
 
-        let inputs: Vec<_> = (0..len).map(|_| self.byte_input()).collect();
+        // One slot per framed byte (nonce counter, tag, ciphertext), plus
+        // a trailing CRC-8 slot.
+        let mut slots = Vec::with_capacity(len as usize + 1);
+        let mut inputs = Vec::with_capacity(len as usize + 1);
+        for seq in 0..=len {
+            let input = self.framed_byte_input();
+            slots.push(self.read_framed_bits(&input, seq));
+            inputs.push(input);
+        }
 
-        let buf = inputs
-            .par_iter()
-            .map(|input| match self.read_bits(input) {
-                Ok(b) => b,
-                Err(e) => {
-                    eprintln!("ERROR: read_bytes: {:?}", e);
-                    b' '
+        let (message, tamper_detected) = self.finalize_slots(&slots);
+
+        Ok(ReadBytesResult {
+            slots,
+            message,
+            tamper_detected,
+            inputs,
+        })
+    }
+
+    /// Re-poll only the slots of `previous` that are not already
+    /// `Recovered`, reusing the exact names `read_bytes` derived for
+    /// them the first time (see `ReadBytesResult::inputs`) instead of
+    /// deriving new ones from the chain, which has since moved on and
+    /// would produce names the writer never poked for this message.
+    /// Updates `previous` in place, including `message`/`tamper_detected`
+    /// once every slot is recovered.
+    pub fn retry_missing(self: &Self, previous: &mut ReadBytesResult) {
+        for seq in 0..previous.slots.len() {
+            if let SlotStatus::Recovered(_) = previous.slots[seq] {
+                continue;
+            }
+            previous.slots[seq] = self.read_framed_bits(&previous.inputs[seq], seq as u8);
+        }
+
+        let (message, tamper_detected) = self.finalize_slots(&previous.slots);
+        previous.message = message;
+        previous.tamper_detected = tamper_detected;
+    }
+
+    /// Check the trailing CRC-8 slot and, if it matches, verify and open
+    /// the AEAD-framed message recovered from `slots`. Shared by
+    /// `read_bytes` and `retry_missing` so both compute the same
+    /// `message`/`tamper_detected` outcome from a slot list.
+    fn finalize_slots(self: &Self, slots: &[SlotStatus]) -> (Option<Vec<u8>>, bool) {
+        let len = slots.len() - 1;
+        let mut framed = Vec::with_capacity(len);
+        let mut all_recovered = true;
+        for slot in &slots[..len] {
+            match *slot {
+                SlotStatus::Recovered(byte) => framed.push(byte),
+                _ => all_recovered = false,
+            }
+        }
+
+        let crc_ok = all_recovered &&
+            match slots[len] {
+                SlotStatus::Recovered(crc) => crc == crc8(&framed),
+                _ => false,
+            };
+
+        if crc_ok {
+            match self.decrypt(&framed) {
+                Ok(plaintext) => (Some(plaintext), false),
+                Err(_) => (None, true),
+            }
+        } else {
+            (None, false)
+        }
+    }
+
+    /// Classify `name` as a cache hit (its bit is 1) or miss, using
+    /// whichever oracle `AutoConfig` decided this server needs.
+    fn is_hit(self: &Self, name: &Name, rtype: RecordType) -> bool {
+        match self.hit_oracle {
+            HitOracle::Timing => {
+                let query_times = self.query_times.expect("query_times");
+                let delay = match self.poke_name(name, rtype) {
+                    Ok(d) => d,
+                    Err(err) => {
+                        debug!("Error reading {} via timing: {}", name, err);
+                        return false;
+                    }
+                };
+                let md = f64::abs(query_times.miss - delay);
+                let hd = f64::abs(query_times.hit - delay);
+                hd < md
+            }
+            HitOracle::SoaCache => {
+                let baseline = match self.baseline_ttl {
+                    Some(ttl) => ttl,
+                    None => return false,
+                };
+                match self.query_name(name, rtype) {
+                    Ok(mut response) => {
+                        response
+                            .take_name_servers()
+                            .iter()
+                            .find(|r| r.rr_type() == RecordType::SOA)
+                            .map(|r| r.ttl() < baseline)
+                            .unwrap_or(false)
+                    }
+                    Err(err) => {
+                        debug!("Error reading {} via SOA cache: {}", name, err);
+                        false
+                    }
                 }
-            })
-            .collect();
+            }
+        }
+    }
 
-        Ok(buf)
+    /// Generate a random, never-before-queried name under whichever
+    /// zone/style `next_xipo` is actually configured to query, for use
+    /// as an SOA-TTL baseline probe. Uses `OsRng`, not the forward-secret
+    /// chain, so measuring it never consumes (or can be predicted from)
+    /// real channel output.
+    fn probe_name(self: &Self) -> (Name, RecordType) {
+        let mut rng = OsRng::new().expect("OsRng::new");
+        let mut buf = [0u8; 16];
+        rng.fill_bytes(&mut buf);
+
+        match self.name_style {
+            NameStyle::Raw => {
+                let label1 = plausible_label(&buf[0..8]);
+                let label2 = plausible_label(&buf[8..16]);
+                let name = format!("{}.{}.xipology.example.com.", label1, label2);
+                (Name::from_str(&name).expect("Name::from_str"), RecordType::SRV)
+            }
+            NameStyle::Camouflage => {
+                let rtype = CAMOUFLAGE_RECORD_TYPES[buf[0] as usize % CAMOUFLAGE_RECORD_TYPES.len()];
+                let domain = &self.base_domains[buf[1] as usize % self.base_domains.len()];
+                let label1 = plausible_label(&buf[2..9]);
+                let label2 = plausible_label(&buf[9..16]);
+                let name = format!("{}.{}.{}", label1, label2, domain);
+                (Name::from_str(&name).expect("Name::from_str"), rtype)
+            }
+        }
     }
 
-    fn poke_name(self: &Self, name: &Name) -> io::Result<f64> {
+    /// Measure the SOA TTL of a fresh name in our own zone, to use as the
+    /// "uncached" baseline `is_hit`'s `HitOracle::SoaCache` branch
+    /// compares against. Unlike `AutoConfig::baseline_ttl` (probed
+    /// against autoconf's own unrelated miss-domain rotation), this
+    /// queries the same zone/style `next_xipo` produces, so the
+    /// comparison is actually meaningful.
+    fn measure_baseline_ttl(self: &Self) -> io::Result<u32> {
+        let (name, rtype) = self.probe_name();
+        let mut response = self.query_name(&name, rtype)?;
+        response
+            .take_name_servers()
+            .iter()
+            .find(|r| r.rr_type() == RecordType::SOA)
+            .map(|r| r.ttl())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no SOA in response"))
+    }
+
+    /// Query `name`, over UDP or TCP depending on what `AutoConfig`
+    /// found `server` to support.
+    fn query_name(self: &Self, name: &Name, rtype: RecordType) -> io::Result<Message> {
         let class = DNSClass::IN;
-        let rtype = RecordType::SRV;
-        let conn = UdpClientConnection::new(self.server)?;
-        let client = SyncClient::new(conn);
+        match self.transport {
+            Transport::Udp => {
+                let conn = UdpClientConnection::new(self.server)?;
+                let client = SyncClient::new(conn);
+                client.query(name, class, rtype).map_err(From::from)
+            }
+            Transport::Tcp => {
+                let conn = TcpClientConnection::new(self.server)?;
+                let client = SyncClient::new(conn);
+                client.query(name, class, rtype).map_err(From::from)
+            }
+        }
+    }
 
+    fn poke_name(self: &Self, name: &Name, rtype: RecordType) -> io::Result<f64> {
         let t1 = time::Instant::now();
-        let _ = client.query(name, class, rtype)?;
+        let _ = self.query_name(name, rtype)?;
         let delay = t1.elapsed();
 
         Ok(duration_to_micros(delay))
     }
 }
 
+/// Derive the payload AEAD key and nonce PRK from `secret` using
+/// `NameDerivator`'s own extract step but dedicated `expand` labels, so
+/// the derived material is independent of (and does not consume) the
+/// `next_name` chain. The nonce PRK is re-`extract`ed from its own
+/// expanded material so `derive_nonce` can vary it per message via
+/// `expand` without ever touching the key.
+fn derive_aead_material(secret: &[u8]) -> ([u8; 32], SigningKey) {
+    let salt = SigningKey::new(&digest::SHA512, b"");
+    let prk = hkdf::extract(&salt, secret);
+
+    let mut key = [0u8; 32];
+    hkdf::expand(&prk, AEAD_KEY_LABEL, &mut key);
+
+    let mut nonce_material = [0u8; 32];
+    hkdf::expand(&prk, AEAD_NONCE_LABEL, &mut nonce_material);
+    let nonce_prk = hkdf::extract(&salt, &nonce_material);
+    shred(&mut nonce_material);
+
+    (key, nonce_prk)
+}
+
+/// Derive the nonce for message number `counter`. Since every message a
+/// `Xipology` instance seals gets a distinct counter value (see
+/// `Xipology::nonce_counter`), one instance never repeats a (key, nonce)
+/// pair — the catastrophic failure mode for ChaCha20-Poly1305. The
+/// counter is not persisted: every instance starts at 0, so two
+/// instances built from the same secret derive the same nonces.
+fn derive_nonce(nonce_prk: &SigningKey, counter: u64) -> [u8; aead::NONCE_LEN] {
+    let mut nonce = [0u8; aead::NONCE_LEN];
+    hkdf::expand(nonce_prk, &counter.to_be_bytes(), &mut nonce);
+    nonce
+}
+
+/// `hkdf::expand` labels used at an epoch boundary. Distinct from each
+/// other and from the (empty) info string `next_name` chains with, so
+/// the root, the chain key and the name stream never mix.
+const EPOCH_CHAIN_LABEL: &[u8] = b"xipology epoch chain v1";
+const EPOCH_ROOT_LABEL: &[u8] = b"xipology epoch root v1";
+
+/// Overwrite ephemeral key material once it has served its purpose.
+fn shred(buf: &mut [u8]) {
+    for b in buf.iter_mut() {
+        *b = 0;
+    }
+}
+
+/// Noise-style ratchet over DNS names. A root key is established once
+/// from the secret; each epoch derives an independent chain key (used
+/// by `next_name` within that epoch) and the next root key from two
+/// separate `expand` labels, then the old chain key is dropped. Because
+/// the root only ever moves forward, compromising the live state does
+/// not expose past epochs' names.
 pub struct NameDerivator {
-    salt: SigningKey,
-    secret: Vec<u8>,
+    root: SigningKey,
+    chain: SigningKey,
+    epoch: u64,
 }
 
 impl NameDerivator {
     pub fn from_secret(secret: &[u8]) -> Self {
         let salt = SigningKey::new(&digest::SHA512, b"");
+        let root = hkdf::extract(&salt, secret);
+        let chain = Self::derive_chain(&root);
+
         Self {
-            salt,
-            secret: secret.to_vec(),
+            root,
+            chain,
+            epoch: 0,
         }
     }
 
+    fn derive_chain(root: &SigningKey) -> SigningKey {
+        let salt = SigningKey::new(&digest::SHA512, b"");
+        let mut material = [0u8; 32];
+        hkdf::expand(root, EPOCH_CHAIN_LABEL, &mut material);
+        let chain = hkdf::extract(&salt, &material);
+        shred(&mut material);
+        chain
+    }
+
+    fn derive_next_root(root: &SigningKey) -> SigningKey {
+        let salt = SigningKey::new(&digest::SHA512, b"");
+        let mut material = [0u8; 32];
+        hkdf::expand(root, EPOCH_ROOT_LABEL, &mut material);
+        let next_root = hkdf::extract(&salt, &material);
+        shred(&mut material);
+        next_root
+    }
+
+    /// Current epoch index, starting at 0.
+    pub fn epoch(self: &Self) -> u64 {
+        self.epoch
+    }
+
+    /// Cross an epoch boundary: derive the next root and this epoch's
+    /// chain key from it, then replace (and so drop) the old chain key.
+    pub fn advance_epoch(self: &mut Self) {
+        let next_root = Self::derive_next_root(&self.root);
+        self.chain = Self::derive_chain(&next_root);
+        self.root = next_root;
+        self.epoch += 1;
+    }
+
     fn hkdf_extract_and_expand(self: &mut Self, out: &mut [u8]) {
-        let prk = hkdf::extract(&self.salt, &self.secret);
+        let prk = hkdf::extract(&self.chain, b"");
         hkdf::expand(&prk, b"", out);
-        self.salt = prk;
+        self.chain = prk;
     }
 
     pub fn next_name(self: &mut Self) -> Name {
@@ -23,6 +23,20 @@ fn print_help(program: &str) {
     eprintln!("{} <dns server ip> <nick>", program);
 }
 
+/// Current rendezvous epoch: seconds since UNIX epoch divided into
+/// `n`-minute buckets. Both ends derive this independently from their
+/// own clock and drive `Xipology::advance_epoch_to` with it, replacing
+/// the old ad-hoc `SecretGen` time-bucket secret.
+fn current_epoch() -> u64 {
+    let n = 5;
+    let secs = Utc::now().timestamp();
+    secs.div(n * 60) as u64
+}
+
+/// How many times a read re-polls slots `read_bytes` came back missing
+/// before moving on and treating this epoch's point as incomplete.
+const READ_RETRIES: u32 = 5;
+
 fn main() {
     env_logger::init().expect("env_logger::init");
 
@@ -46,25 +60,42 @@ fn main() {
     };
 
     let base_secret = "rendezvous";
-    let mut xipo = xipolib::Xipology::from_secret(server, From::from(base_secret));
-    let mut secret = SecretGen::from(base_secret);
+    let mut xipo = xipolib::Xipology::from_secret(server, From::from(base_secret))
+        .expect("Xipology::from_secret");
 
     let mut rng = OsRng::new().expect("OsRng::new");
     let mut encounters: HashSet<String> = HashSet::new();
 
     loop {
-        let next = secret.secret();
-        println!(
-            "Reading rendezvous point {}",
-            std::str::from_utf8(&next).expect("from_utf8")
-        );
-        xipo.change_secret(next);
+        xipo.advance_epoch_to(current_epoch());
+        println!("Reading rendezvous point (epoch {})", xipo.epoch());
 
         match xipo.read_bytes() {
-            Ok(found_nicks) => {
-                if let Ok(as_str) = std::str::from_utf8(&found_nicks) {
-                    eprintln!("Read something: {}", as_str);
-                    encounters.extend(decode_nicks(as_str.as_bytes()));
+            Ok(mut result) => {
+                for _ in 0..READ_RETRIES {
+                    if result.message.is_some() || result.tamper_detected ||
+                        result.missing_seqs().is_empty()
+                    {
+                        break;
+                    }
+                    eprintln!("Missing slots {:?}, re-polling...", result.missing_seqs());
+                    xipo.retry_missing(&mut result);
+                }
+                if let Some(found_nicks) = result.message {
+                    if let Ok(as_str) = std::str::from_utf8(&found_nicks) {
+                        eprintln!("Read something: {}", as_str);
+                        encounters.extend(decode_nicks(as_str.as_bytes()));
+                    }
+                } else if result.tamper_detected {
+                    eprintln!(
+                        "TAMPERED: rendezvous point's slots all recovered but the AEAD tag \
+                         did not verify, discarding"
+                    );
+                } else {
+                    eprintln!(
+                        "Read incomplete, missing slots: {:?}",
+                        result.missing_seqs()
+                    );
                 }
                 continue;
             }
@@ -73,7 +104,6 @@ fn main() {
 
                 // Write back all our encountered nicks and our own
                 encounters.insert(nick.clone());
-                xipo.change_secret(secret.secret());
                 let nicks = encode_nicks(&encounters);
                 let _ = xipo.write_bytes(&nicks);
                 eprintln!("Scribbled on ether: {:?}", encounters);
@@ -94,43 +124,6 @@ fn main() {
     }
 }
 
-struct SecretGen<'a> {
-    secret: &'a str,
-    used_time: String,
-    counter: usize,
-}
-
-impl<'a> SecretGen<'a> {
-    pub fn from(secret: &'a str) -> Self {
-        let time = Self::get_time();
-        Self {
-            secret: secret,
-            used_time: time,
-            counter: 0,
-        }
-    }
-
-    pub fn secret(self: &mut Self) -> Vec<u8> {
-        let time = Self::get_time();
-        if time != self.used_time {
-            self.counter = 0;
-            self.used_time = time.clone();
-        }
-        let secret = format!("{}-{}-{}", self.secret, time, self.counter);
-        self.counter += 1;
-        secret.into_bytes()
-    }
-
-    pub fn get_time() -> String {
-        // Seconds since UNIX epoch divided by n minutes
-        let epoch = Utc::now().timestamp();
-        let n = 5;
-        let time = epoch.div(n * 60);
-        format!("{}", time)
-    }
-}
-
-
 fn encode_nicks(nicks: &HashSet<String>) -> Vec<u8> {
     let nicks: String = nicks.iter().cloned().collect::<Vec<_>>().join(", ");
     nicks.into_bytes()
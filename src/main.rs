@@ -9,11 +9,19 @@ extern crate trust_dns;
 extern crate xipolib;
 
 use std::io::{self, Read};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::thread;
+
+use trust_dns::rr::Name;
 
 // mod autoconf;
 
 fn print_help(program: &str) {
-    eprintln!("{} <dns server ip> <secret> <read | write <text>>", program);
+    eprintln!(
+        "{} <dns server ip> <secret> <read | write <text>> [responder bind addr]",
+        program
+    );
 }
 
 enum Op {
@@ -21,6 +29,10 @@ enum Op {
     Write,
 }
 
+/// How many times `Op::Read` re-polls slots `read_bytes` came back
+/// missing before giving up on the message.
+const READ_RETRIES: u32 = 5;
+
 fn main() {
     env_logger::init().expect("env_logger::init");
 
@@ -60,24 +72,69 @@ fn main() {
         }
     };
 
-    let mut xipo = xipolib::Xipology::from_secret(server, &secret).expect("Xipology::from_secret");
+    let mut xipo = xipolib::Xipology::from_secret(server, secret).expect("Xipology::from_secret");
+
+    // Optional: run our own authoritative responder for the zone and
+    // prime it with the exact names we're about to poke, so the target
+    // resolver gets a real, TTL-controlled answer instead of depending
+    // on however `xipology.example.com`'s actual operators answer.
+    let responder_bind = args.next();
 
     match op {
         Op::Read => {
             eprint!("Reading...");
-            let bytes = xipo.read_bytes().expect("xipo.read_bytes");
+            let mut result = xipo.read_bytes().expect("xipo.read_bytes");
+            for _ in 0..READ_RETRIES {
+                if result.message.is_some() || result.tamper_detected ||
+                    result.missing_seqs().is_empty()
+                {
+                    break;
+                }
+                eprintln!("Missing slots {:?}, re-polling...", result.missing_seqs());
+                xipo.retry_missing(&mut result);
+            }
             eprintln!("Done!");
-            let result = std::str::from_utf8(&bytes).expect("from_utf8");
-            eprintln!("Received:");
-            println!("{}", result);
+            match result.message {
+                Some(bytes) => {
+                    let text = std::str::from_utf8(&bytes).expect("from_utf8");
+                    eprintln!("Received:");
+                    println!("{}", text);
+                }
+                None if result.tamper_detected => {
+                    eprintln!("TAMPERED: all slots recovered but the AEAD tag did not verify");
+                }
+                None => {
+                    eprintln!(
+                        "Incomplete read, missing slots: {:?}",
+                        result.missing_seqs()
+                    );
+                }
+            }
         }
         Op::Write => {
             let mut buffer = String::new();
             io::stdin().read_to_string(&mut buffer).expect(
                 "stdin.read_to_string",
             );
+
+            let plan = xipo.plan_write_bytes(buffer.as_bytes());
+
+            if let Some(bind_addr) = responder_bind {
+                let zone = Name::from_str("xipology.example.com.").expect("Name::from_str");
+                let responder = Arc::new(xipolib::Responder::new(zone, 300));
+                for &(ref name, rtype) in &plan {
+                    responder.insert(name.clone(), rtype, xipolib::default_rdata(rtype), 300);
+                }
+
+                let serving = Arc::clone(&responder);
+                let bind_addr = bind_addr.parse().expect("bind_addr parse");
+                thread::spawn(move || if let Err(err) = serving.serve(bind_addr) {
+                    eprintln!("responder error: {}", err);
+                });
+            }
+
             eprint!("Writing...");
-            let _ = xipo.write_bytes(buffer.as_bytes());
+            let _ = xipo.write_planned(&plan);
             eprintln!("Done!");
         }
     }